@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: Copyright (c) 2024-2025 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::Result;
 use dynamo_runtime::{
@@ -22,21 +22,25 @@ pub mod publisher;
 pub mod recorder;
 pub mod scheduler;
 pub mod scoring;
+pub mod scrubber;
+pub mod worker_manager;
 
 use crate::{
     kv_router::{
         indexer::{KvIndexer, KvIndexerInterface, RouterEvent},
         metrics_aggregator::KvMetricsAggregator,
         protocols::{LocalBlockHash, RouterRequest, RouterResponse, WorkerSelectionResult},
-        scheduler::{KvScheduler, KvSchedulerError, SchedulingRequest},
+        scheduler::{KvScheduler, KvSchedulerError, SchedulerAdmissionConfig, SchedulingRequest},
         scoring::ProcessedEndpoints,
+        scrubber::{Scrubber, ScrubberConfig},
+        worker_manager::{TaskState, WorkerManager},
     },
     preprocessor::PreprocessedRequest,
     protocols::common::llm_backend::LLMEngineOutput,
     tokens::TokenBlockSequence,
 };
 
-use dynamo_runtime::traits::events::EventSubscriber;
+use dynamo_runtime::traits::events::{EventPublisher, EventSubscriber};
 
 // [gluo TODO] shouldn't need to be public
 // this should be discovered from the component
@@ -44,6 +48,13 @@ pub const KV_EVENT_SUBJECT: &str = "kv_events";
 pub const KV_HIT_RATE_SUBJECT: &str = "kv-hit-rate";
 pub const KV_METRICS_ENDPOINT: &str = "load_metrics";
 
+/// Name the KV event-ingest loop is registered under with the
+/// [`WorkerManager`], surfaced through [`KvRouter::worker_status`].
+pub const KV_EVENT_INGEST_TASK: &str = "kv-event-ingest";
+/// Name the resync-request dispatch loop is registered under with the
+/// [`WorkerManager`], surfaced through [`KvRouter::worker_status`].
+pub const KV_RESYNC_DISPATCH_TASK: &str = "kv-resync-dispatch";
+
 /// A trait that users can implement to define custom selection logic
 pub trait WorkerSelector {
     fn select_worker(
@@ -68,6 +79,16 @@ pub struct KvRouterConfig {
     /// Weight for waiting requests in worker selection.
     /// Higher values avoid workers with queued requests. Default: 1.0
     pub waiting_requests_weight: f64,
+
+    /// A worker is considered saturated once its `gpu_cache_usage` is at or
+    /// above this threshold (0.0-1.0). Requests are parked in the admission
+    /// queue instead of being placed while every worker is saturated.
+    /// Default: 0.95
+    pub saturation_threshold: f64,
+
+    /// Maximum number of requests the admission queue will hold before new
+    /// requests are rejected with `KvSchedulerError::QueueFull`. Default: 1024
+    pub max_queue_len: usize,
 }
 
 impl Default for KvRouterConfig {
@@ -76,6 +97,8 @@ impl Default for KvRouterConfig {
             overlap_score_weight: 1.0,
             gpu_cache_usage_weight: 1.0,
             waiting_requests_weight: 1.0,
+            saturation_threshold: 0.95,
+            max_queue_len: 1024,
         }
     }
 }
@@ -95,6 +118,7 @@ impl KvRouterConfig {
                 .unwrap_or(default.gpu_cache_usage_weight),
             waiting_requests_weight: waiting_requests_weight
                 .unwrap_or(default.waiting_requests_weight),
+            ..default
         }
     }
 }
@@ -102,9 +126,11 @@ impl KvRouterConfig {
 /// A KvRouter only decides which worker you should use. It doesn't send you there.
 /// TODO: Rename this to indicate it only selects a worker, it does not route.
 pub struct KvRouter {
-    indexer: KvIndexer,
+    indexer: Arc<KvIndexer>,
     scheduler: KvScheduler,
+    scrubber: Scrubber,
     block_size: usize,
+    worker_manager: Arc<WorkerManager>,
 }
 
 impl KvRouter {
@@ -112,6 +138,15 @@ impl KvRouter {
         component: Component,
         block_size: usize,
         selector: Option<Box<dyn WorkerSelector + Send + Sync>>,
+    ) -> Result<Self> {
+        Self::new_with_config(component, block_size, selector, KvRouterConfig::default()).await
+    }
+
+    pub async fn new_with_config(
+        component: Component,
+        block_size: usize,
+        selector: Option<Box<dyn WorkerSelector + Send + Sync>>,
+        router_config: KvRouterConfig,
     ) -> Result<Self> {
         let cancellation_token = component
             .drt()
@@ -119,43 +154,109 @@ impl KvRouter {
             .expect("Cannot KV route static workers")
             .primary_token();
         tracing::info!("KV Routing initialized");
-        let metrics_aggregator =
-            KvMetricsAggregator::new(component.clone(), cancellation_token.clone()).await;
-        let indexer = KvIndexer::new(cancellation_token.clone(), block_size);
-        let scheduler = KvScheduler::start(
+        let worker_manager = Arc::new(WorkerManager::new(cancellation_token.clone()));
+        let metrics_aggregator = KvMetricsAggregator::new(
+            component.clone(),
+            cancellation_token.clone(),
+            worker_manager.clone(),
+        )
+        .await;
+        let (indexer, resync_rx) =
+            KvIndexer::new(cancellation_token.clone(), block_size, worker_manager.clone()).await;
+        let indexer = Arc::new(indexer);
+        let resync_rx = Arc::new(tokio::sync::Mutex::new(resync_rx));
+        let scheduler_config = SchedulerAdmissionConfig {
+            saturation_threshold: router_config.saturation_threshold,
+            max_queue_len: router_config.max_queue_len,
+            ..Default::default()
+        };
+        let scheduler = KvScheduler::start_with_config(
             component.namespace().clone(),
             block_size,
             metrics_aggregator.endpoints_watcher(),
             selector,
+            scheduler_config,
+            worker_manager.clone(),
         )
         .await?;
 
-        // [gluo TODO] try subscribe_with_type::<RouterEvent>,
-        // error checking below will be different.
-        let mut kv_events_rx = component.subscribe(KV_EVENT_SUBJECT).await?;
+        // Re-subscribing from scratch on every attempt means a dropped NATS
+        // subscription is recovered automatically by the worker manager
+        // instead of silently leaving the index fed by a dead stream.
         let kv_events_tx = indexer.event_sender();
+        worker_manager
+            .spawn(KV_EVENT_INGEST_TASK, {
+                let component = component.clone();
+                move || {
+                    let component = component.clone();
+                    let kv_events_tx = kv_events_tx.clone();
+                    async move {
+                        // [gluo TODO] try subscribe_with_type::<RouterEvent>,
+                        // error checking below will be different.
+                        let mut kv_events_rx = component.subscribe(KV_EVENT_SUBJECT).await?;
+                        while let Some(event) = kv_events_rx.next().await {
+                            let event: RouterEvent = match serde_json::from_slice(&event.payload) {
+                                Ok(event) => event,
+                                Err(e) => {
+                                    tracing::warn!("Failed to deserialize RouterEvent: {:?}", e);
+                                    // Choosing warn and continue to process other events from other workers
+                                    // A bad event likely signals a problem with a worker, but potentially other workers are still healthy
+                                    continue;
+                                }
+                            };
+                            if kv_events_tx.send(event).await.is_err() {
+                                tracing::debug!("indexer dropped; shutting down kv event ingest");
+                                return Ok(());
+                            }
+                        }
+                        // The subscription stream ended: report a failure so the
+                        // worker manager re-subscribes after its backoff.
+                        Err(anyhow::anyhow!("kv event subscription closed"))
+                    }
+                }
+            })
+            .await;
 
-        tokio::spawn(async move {
-            while let Some(event) = kv_events_rx.next().await {
-                let event: RouterEvent = match serde_json::from_slice(&event.payload) {
-                    Ok(event) => event,
-                    Err(e) => {
-                        tracing::warn!("Failed to deserialize RouterEvent: {:?}", e);
-                        // Choosing warn and continue to process other events from other workers
-                        // A bad event likely signals a problem with a worker, but potentially other workers are still healthy
-                        continue;
+        // Forward resync requests the indexer raised after detecting a gap
+        // in a worker's event sequence onto the KV event subject, so the
+        // worker can reply with a `Snapshot` of its current block hashes.
+        worker_manager
+            .spawn(KV_RESYNC_DISPATCH_TASK, {
+                let component = component.clone();
+                let resync_rx = resync_rx.clone();
+                move || {
+                    let component = component.clone();
+                    let resync_rx = resync_rx.clone();
+                    async move {
+                        let mut resync_rx = resync_rx.lock().await;
+                        while let Some(request) = resync_rx.recv().await {
+                            tracing::info!(
+                                worker_id = request.worker_id,
+                                "requesting KV cache resync after detecting a sequence gap"
+                            );
+                            let payload = serde_json::to_vec(&request)?;
+                            component.publish(KV_EVENT_SUBJECT, &payload).await?;
+                        }
+                        Ok(())
                     }
-                };
-                if let Err(e) = kv_events_tx.send(event).await {
-                    tracing::debug!("failed to send kv event to indexer; shutting down: {:?}", e);
                 }
-            }
-        });
+            })
+            .await;
+
+        let scrubber = Scrubber::start(
+            indexer.clone(),
+            metrics_aggregator.endpoints_watcher(),
+            ScrubberConfig::default(),
+            worker_manager.clone(),
+        )
+        .await;
 
         Ok(Self {
             scheduler,
             indexer,
+            scrubber,
             block_size,
+            worker_manager,
         })
     }
 
@@ -195,10 +296,80 @@ impl KvRouter {
         Ok((worker_id, overlap_amount))
     }
 
+    /// Given a micro-batch of token sequences, find the best-match worker
+    /// for each in a single indexer pass and a single scheduling pass, so a
+    /// burst of requests doesn't all stampede onto the same momentarily-best
+    /// worker. Returned overlap amounts are in number of blocks, one pair
+    /// per input sequence in the same order.
+    async fn find_best_matches(&self, requests: &[&[u32]]) -> anyhow::Result<Vec<(i64, u32)>> {
+        let block_size = self.block_size;
+        let isl_lens: Vec<usize> = requests.iter().map(|tokens| tokens.len()).collect();
+
+        let block_hashes_batch: Vec<Vec<LocalBlockHash>> = requests
+            .iter()
+            .map(|tokens| {
+                let (complete_blocks, _partial_block) =
+                    TokenBlockSequence::split_tokens(tokens, block_size, 1337_u64);
+                complete_blocks
+                    .into_iter()
+                    .map(|block| LocalBlockHash(block.block_hash()))
+                    .collect()
+            })
+            .collect();
+
+        let overlap_scores_batch = self.indexer.find_matches_batch(block_hashes_batch).await?;
+        let scheduling_requests = overlap_scores_batch
+            .iter()
+            .cloned()
+            .zip(isl_lens)
+            .map(|(overlap_scores, isl_tokens)| SchedulingRequest {
+                overlap_scores,
+                isl_tokens,
+            })
+            .collect();
+
+        let worker_ids = self.scheduler.schedule_batch(scheduling_requests).await?;
+        Ok(worker_ids
+            .into_iter()
+            .zip(overlap_scores_batch)
+            .map(|(worker_id, overlap_scores)| {
+                let overlap_amount = overlap_scores.scores.get(&worker_id).copied().unwrap_or(0);
+                (worker_id, overlap_amount)
+            })
+            .collect())
+    }
+
     /// Get the block size this router was configured with
     pub fn block_size(&self) -> usize {
         self.block_size
     }
+
+    /// Number of requests currently parked in the scheduler's admission
+    /// queue, waiting for a worker to free up capacity.
+    pub fn queue_depth(&self) -> usize {
+        self.scheduler.queue_depth()
+    }
+
+    /// Live state of the router's supervised background tasks (the event
+    /// ingest loop, the resync dispatch loop, the indexer's event fold loop,
+    /// the metrics aggregator, the scheduler's queue watcher, the scrubber),
+    /// keyed by task name, so operators can tell whether the index is
+    /// actually being fed.
+    pub async fn worker_status(&self) -> HashMap<String, TaskState> {
+        self.worker_manager.status().await
+    }
+
+    /// Trigger an indexer scrub immediately instead of waiting for the
+    /// background scrubber's next tick.
+    pub async fn scrub_now(&self) {
+        self.scrubber.scrub_now().await
+    }
+
+    /// Total number of stale/dead-worker block hashes evicted from the
+    /// indexer since startup.
+    pub fn evicted_blocks_total(&self) -> u64 {
+        self.scrubber.evicted_total()
+    }
 }
 
 #[async_trait]
@@ -229,6 +400,17 @@ impl KvPushRouter {
     ) -> Self {
         KvPushRouter { inner, chooser }
     }
+
+    /// Place a whole micro-batch of token sequences in one scheduling pass,
+    /// for upstream callers that already group requests into batches.
+    /// Returned `(worker_id, overlap_amount)` pairs are in the same order as
+    /// `requests`.
+    pub async fn find_best_matches(
+        &self,
+        requests: &[&[u32]],
+    ) -> anyhow::Result<Vec<(i64, u32)>> {
+        self.chooser.find_best_matches(requests).await
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,28 @@
+// SPDX-FileCopyrightText: Copyright (c) 2024-2025 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Hash of a single block of tokens, computed locally by the preprocessor
+/// using the same block size and seed as the indexer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LocalBlockHash(pub u64);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouterRequest {
+    pub tokens: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouterResponse {
+    pub worker_id: i64,
+}
+
+/// Outcome of running worker-selection logic against a set of processed
+/// endpoints for a single scheduling request.
+#[derive(Debug, Clone)]
+pub struct WorkerSelectionResult {
+    pub worker_id: i64,
+    pub required_blocks: u64,
+    pub overlap_blocks: u32,
+}
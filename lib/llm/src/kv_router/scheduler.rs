@@ -0,0 +1,519 @@
+// SPDX-FileCopyrightText: Copyright (c) 2024-2025 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::Result as AnyhowResult;
+use dynamo_runtime::component::Namespace;
+use thiserror::Error;
+use tokio::sync::{oneshot, watch, Mutex};
+use tokio::time::timeout;
+
+use crate::kv_router::{
+    indexer::OverlapScores,
+    protocols::WorkerSelectionResult,
+    scoring::{Endpoint, ProcessedEndpoints},
+    worker_manager::WorkerManager,
+    WorkerSelector,
+};
+
+/// Name the admission-queue drain loop is registered under with the
+/// [`WorkerManager`], surfaced through `KvRouter::worker_status`.
+pub const SCHEDULER_QUEUE_WATCHER_TASK: &str = "kv-scheduler-queue-watcher";
+
+#[derive(Debug, Error)]
+pub enum KvSchedulerError {
+    #[error("no workers are registered with the scheduler")]
+    NoWorkers,
+    #[error("admission queue is full ({0} requests already waiting)")]
+    QueueFull(usize),
+    #[error("request timed out waiting for a worker to free up capacity")]
+    AdmissionTimeout,
+    #[error("worker selection failed: {0}")]
+    Selection(String),
+}
+
+/// A single scheduling decision request: the overlap scores the indexer
+/// computed for this sequence, plus its ISL length (used for worker
+/// selection and to prioritize the admission queue).
+#[derive(Debug, Clone)]
+pub struct SchedulingRequest {
+    pub overlap_scores: OverlapScores,
+    pub isl_tokens: usize,
+}
+
+/// Tunables for the scheduler's admission queue.
+#[derive(Debug, Clone)]
+pub struct SchedulerAdmissionConfig {
+    /// A worker is considered saturated once its `gpu_cache_usage` is at or
+    /// above this threshold (0.0-1.0). Default: 0.95
+    pub saturation_threshold: f64,
+    /// Maximum number of requests parked in the admission queue before
+    /// `schedule` rejects new requests with `QueueFull`. Default: 1024
+    pub max_queue_len: usize,
+    /// How long a request may wait in the admission queue before `schedule`
+    /// gives up and returns `AdmissionTimeout`. Default: 30s
+    pub admission_timeout: Duration,
+}
+
+impl Default for SchedulerAdmissionConfig {
+    fn default() -> Self {
+        Self {
+            saturation_threshold: 0.95,
+            max_queue_len: 1024,
+            admission_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+struct QueuedRequest {
+    /// Identifies this entry so a timed-out `schedule` call can prune itself
+    /// from the queue instead of lingering until `drain` happens to reach it.
+    id: u64,
+    request: SchedulingRequest,
+    responder: oneshot::Sender<Result<i64, KvSchedulerError>>,
+}
+
+struct Inner {
+    block_size: usize,
+    selector: Box<dyn WorkerSelector + Send + Sync>,
+    config: SchedulerAdmissionConfig,
+    queue: Mutex<VecDeque<QueuedRequest>>,
+    queue_depth: AtomicUsize,
+    next_request_id: AtomicU64,
+}
+
+impl Inner {
+    /// Whether every currently-registered worker is at or above the
+    /// saturation threshold. Callers must check for an empty worker set
+    /// themselves first: that's a `NoWorkers` condition, not saturation, and
+    /// should fail fast instead of parking in the admission queue.
+    fn is_saturated(&self, endpoints: &ProcessedEndpoints) -> bool {
+        !endpoints
+            .endpoints
+            .values()
+            .any(|e| saturation(e) < self.config.saturation_threshold)
+    }
+
+    fn select(
+        &self,
+        request: &SchedulingRequest,
+        endpoints: &ProcessedEndpoints,
+    ) -> Result<WorkerSelectionResult, KvSchedulerError> {
+        self.selector
+            .select_worker(endpoints, request, self.block_size)
+            .map_err(|e| KvSchedulerError::Selection(e.to_string()))
+    }
+
+    /// Try to hand every admissible request at the front of the queue a
+    /// worker. The simulated load is updated after each placement the same
+    /// way `schedule_batch` does, so consecutive placements spread across
+    /// workers instead of every one landing on whichever looked best before
+    /// any of them were accounted for.
+    async fn drain(&self, endpoints: &ProcessedEndpoints) {
+        let mut simulated = endpoints.clone();
+        let mut queue = self.queue.lock().await;
+        while !self.is_saturated(&simulated) {
+            let Some(queued) = queue.pop_front() else {
+                break;
+            };
+            let result = self.select(&queued.request, &simulated);
+            if let Ok(selection) = &result {
+                if let Some(endpoint) = simulated.endpoints.get_mut(&selection.worker_id) {
+                    endpoint.waiting_requests += 1;
+                }
+            }
+            let _ = queued
+                .responder
+                .send(result.map(|selection| selection.worker_id));
+        }
+        self.queue_depth.store(queue.len(), Ordering::Relaxed);
+    }
+
+    /// Remove the queued entry with `id`, if it's still waiting. Used to
+    /// prune a request that gave up (its `schedule` call's admission timeout
+    /// elapsed) so it doesn't keep counting against `max_queue_len` until
+    /// `drain` happens to reach it.
+    async fn prune(&self, id: u64) {
+        let mut queue = self.queue.lock().await;
+        queue.retain(|queued| queued.id != id);
+        self.queue_depth.store(queue.len(), Ordering::Relaxed);
+    }
+}
+
+fn saturation(endpoint: &Endpoint) -> f64 {
+    endpoint.gpu_cache_usage
+}
+
+/// Load score used to rank workers during selection: GPU cache usage plus a
+/// small penalty per already-assumed waiting request, so a burst of
+/// placements within the same batch spreads out instead of all landing on
+/// whichever worker looked best before any of them were accounted for.
+fn combined_load(endpoint: &Endpoint) -> f64 {
+    endpoint.gpu_cache_usage + endpoint.waiting_requests as f64 * 0.01
+}
+
+/// Insert `item` ordered by descending ISL length, so the longest-prompt
+/// requests (the ones with the most to lose by waiting) drain first; ties
+/// preserve FIFO order.
+fn insert_by_priority(queue: &mut VecDeque<QueuedRequest>, item: QueuedRequest) {
+    let pos = queue
+        .iter()
+        .position(|queued| queued.request.isl_tokens < item.request.isl_tokens)
+        .unwrap_or(queue.len());
+    queue.insert(pos, item);
+}
+
+pub struct KvScheduler {
+    inner: Arc<Inner>,
+    endpoints: watch::Receiver<ProcessedEndpoints>,
+}
+
+impl KvScheduler {
+    pub async fn start(
+        namespace: Namespace,
+        block_size: usize,
+        endpoints: watch::Receiver<ProcessedEndpoints>,
+        selector: Option<Box<dyn WorkerSelector + Send + Sync>>,
+        worker_manager: Arc<WorkerManager>,
+    ) -> AnyhowResult<Self> {
+        Self::start_with_config(
+            namespace,
+            block_size,
+            endpoints,
+            selector,
+            SchedulerAdmissionConfig::default(),
+            worker_manager,
+        )
+        .await
+    }
+
+    pub async fn start_with_config(
+        _namespace: Namespace,
+        block_size: usize,
+        endpoints: watch::Receiver<ProcessedEndpoints>,
+        selector: Option<Box<dyn WorkerSelector + Send + Sync>>,
+        config: SchedulerAdmissionConfig,
+        worker_manager: Arc<WorkerManager>,
+    ) -> AnyhowResult<Self> {
+        let selector = selector.unwrap_or_else(|| Box::new(DefaultWorkerSelector));
+        let inner = Arc::new(Inner {
+            block_size,
+            selector,
+            config,
+            queue: Mutex::new(VecDeque::new()),
+            queue_depth: AtomicUsize::new(0),
+            next_request_id: AtomicU64::new(0),
+        });
+
+        // Drain the admission queue reactively whenever the metrics
+        // aggregator reports a change in worker load.
+        worker_manager
+            .spawn(SCHEDULER_QUEUE_WATCHER_TASK, {
+                let inner = inner.clone();
+                let endpoints = endpoints.clone();
+                move || {
+                    let inner = inner.clone();
+                    let mut endpoints_rx = endpoints.clone();
+                    async move {
+                        while endpoints_rx.changed().await.is_ok() {
+                            let snapshot = endpoints_rx.borrow_and_update().clone();
+                            inner.drain(&snapshot).await;
+                        }
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+
+        Ok(Self { inner, endpoints })
+    }
+
+    /// Number of requests currently parked in the admission queue, waiting
+    /// for worker capacity to free up.
+    pub fn queue_depth(&self) -> usize {
+        self.inner.queue_depth.load(Ordering::Relaxed)
+    }
+
+    pub async fn schedule(
+        &self,
+        overlap_scores: OverlapScores,
+        isl_tokens: usize,
+    ) -> Result<i64, KvSchedulerError> {
+        let request = SchedulingRequest {
+            overlap_scores,
+            isl_tokens,
+        };
+        let endpoints = self.endpoints.borrow().clone();
+
+        if endpoints.endpoints.is_empty() {
+            return Err(KvSchedulerError::NoWorkers);
+        }
+
+        if !self.inner.is_saturated(&endpoints) {
+            let selection = self.inner.select(&request, &endpoints)?;
+            return Ok(selection.worker_id);
+        }
+
+        // Every worker is saturated: park the request instead of forcing a
+        // placement onto an overloaded endpoint.
+        let (tx, rx) = oneshot::channel();
+        let id = self.inner.next_request_id.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut queue = self.inner.queue.lock().await;
+            if queue.len() >= self.inner.config.max_queue_len {
+                return Err(KvSchedulerError::QueueFull(queue.len()));
+            }
+            insert_by_priority(
+                &mut queue,
+                QueuedRequest {
+                    id,
+                    request,
+                    responder: tx,
+                },
+            );
+            self.inner.queue_depth.store(queue.len(), Ordering::Relaxed);
+        }
+
+        match timeout(self.inner.config.admission_timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(KvSchedulerError::AdmissionTimeout),
+            Err(_) => {
+                // Timed out: prune ourselves from the queue instead of
+                // lingering there (still counting against max_queue_len)
+                // until drain happens to pop us and discover the responder
+                // is gone.
+                self.inner.prune(id).await;
+                Err(KvSchedulerError::AdmissionTimeout)
+            }
+        }
+    }
+
+    /// Place a whole micro-batch in one scheduling pass: each placement
+    /// accounts for the load the ones before it in the batch would add, so
+    /// the batch spreads across workers instead of stampeding onto whichever
+    /// one looked best before any of them were placed. Saturation is judged
+    /// on `gpu_cache_usage` alone, which this running estimate does not
+    /// change: if every worker is saturated in the snapshot taken at the
+    /// start of the call, each request goes through `schedule` (and its
+    /// admission queue) instead.
+    pub async fn schedule_batch(
+        &self,
+        requests: Vec<SchedulingRequest>,
+    ) -> Result<Vec<i64>, KvSchedulerError> {
+        let mut endpoints = self.endpoints.borrow().clone();
+
+        if endpoints.endpoints.is_empty() {
+            return Err(KvSchedulerError::NoWorkers);
+        }
+
+        let mut worker_ids = Vec::with_capacity(requests.len());
+
+        for request in requests {
+            if self.inner.is_saturated(&endpoints) {
+                let worker_id = self
+                    .schedule(request.overlap_scores, request.isl_tokens)
+                    .await?;
+                worker_ids.push(worker_id);
+                continue;
+            }
+
+            let selection = self.inner.select(&request, &endpoints)?;
+            if let Some(endpoint) = endpoints.endpoints.get_mut(&selection.worker_id) {
+                endpoint.waiting_requests += 1;
+            }
+            worker_ids.push(selection.worker_id);
+        }
+
+        Ok(worker_ids)
+    }
+}
+
+struct DefaultWorkerSelector;
+
+impl WorkerSelector for DefaultWorkerSelector {
+    fn select_worker(
+        &self,
+        workers: &ProcessedEndpoints,
+        request: &SchedulingRequest,
+        _block_size: usize,
+    ) -> Result<WorkerSelectionResult, KvSchedulerError> {
+        let best = workers
+            .endpoints
+            .values()
+            .min_by(|a, b| combined_load(a).partial_cmp(&combined_load(b)).unwrap())
+            .ok_or(KvSchedulerError::NoWorkers)?;
+        Ok(WorkerSelectionResult {
+            worker_id: best.worker_id,
+            required_blocks: 0,
+            overlap_blocks: request
+                .overlap_scores
+                .scores
+                .get(&best.worker_id)
+                .copied()
+                .unwrap_or(0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(worker_id: i64, gpu_cache_usage: f64) -> Endpoint {
+        Endpoint {
+            worker_id,
+            gpu_cache_usage,
+            waiting_requests: 0,
+        }
+    }
+
+    fn endpoints(list: Vec<Endpoint>) -> ProcessedEndpoints {
+        ProcessedEndpoints {
+            endpoints: list.into_iter().map(|e| (e.worker_id, e)).collect(),
+        }
+    }
+
+    fn inner_with_threshold(saturation_threshold: f64) -> Inner {
+        Inner {
+            block_size: 16,
+            selector: Box::new(DefaultWorkerSelector),
+            config: SchedulerAdmissionConfig {
+                saturation_threshold,
+                ..SchedulerAdmissionConfig::default()
+            },
+            queue: Mutex::new(VecDeque::new()),
+            queue_depth: AtomicUsize::new(0),
+            next_request_id: AtomicU64::new(0),
+        }
+    }
+
+    #[test]
+    fn is_saturated_false_when_any_worker_below_threshold() {
+        let inner = inner_with_threshold(0.9);
+        let eps = endpoints(vec![endpoint(1, 0.95), endpoint(2, 0.5)]);
+        assert!(!inner.is_saturated(&eps));
+    }
+
+    #[test]
+    fn is_saturated_true_when_every_worker_at_or_above_threshold() {
+        let inner = inner_with_threshold(0.9);
+        let eps = endpoints(vec![endpoint(1, 0.95), endpoint(2, 0.9)]);
+        assert!(inner.is_saturated(&eps));
+    }
+
+    #[test]
+    fn is_saturated_true_for_empty_endpoint_set() {
+        // is_saturated no longer special-cases this: callers (schedule,
+        // schedule_batch) must check for an empty set themselves and return
+        // NoWorkers instead of queueing on it.
+        let inner = inner_with_threshold(0.9);
+        assert!(inner.is_saturated(&ProcessedEndpoints::default()));
+    }
+
+    fn queued(isl_tokens: usize) -> QueuedRequest {
+        let (responder, _rx) = oneshot::channel();
+        QueuedRequest {
+            id: 0,
+            request: SchedulingRequest {
+                overlap_scores: OverlapScores::default(),
+                isl_tokens,
+            },
+            responder,
+        }
+    }
+
+    #[test]
+    fn insert_by_priority_orders_by_descending_isl_with_fifo_ties() {
+        let mut queue = VecDeque::new();
+        insert_by_priority(&mut queue, queued(10));
+        insert_by_priority(&mut queue, queued(50));
+        insert_by_priority(&mut queue, queued(30));
+        insert_by_priority(&mut queue, queued(30));
+
+        let isl_order: Vec<usize> = queue.iter().map(|q| q.request.isl_tokens).collect();
+        assert_eq!(isl_order, vec![50, 30, 30, 10]);
+    }
+
+    #[tokio::test]
+    async fn drain_spreads_placements_instead_of_stampeding_one_worker() {
+        let inner = inner_with_threshold(0.99);
+        // Worker 2 looks best, but not by much: a handful of placements
+        // should tip its simulated load past worker 1's, so the rest of the
+        // queue doesn't all land on worker 2.
+        let eps = endpoints(vec![endpoint(1, 0.125), endpoint(2, 0.10)]);
+
+        let mut receivers = Vec::new();
+        {
+            let mut queue = inner.queue.lock().await;
+            for (id, isl_tokens) in [10, 20, 30, 40, 50].into_iter().enumerate() {
+                let (responder, rx) = oneshot::channel();
+                receivers.push(rx);
+                insert_by_priority(
+                    &mut queue,
+                    QueuedRequest {
+                        id: id as u64,
+                        request: SchedulingRequest {
+                            overlap_scores: OverlapScores::default(),
+                            isl_tokens,
+                        },
+                        responder,
+                    },
+                );
+            }
+        }
+
+        inner.drain(&eps).await;
+
+        let mut worker_ids = Vec::new();
+        for rx in receivers {
+            worker_ids.push(rx.await.unwrap().unwrap());
+        }
+        assert!(worker_ids.contains(&1), "{worker_ids:?}");
+        assert!(worker_ids.contains(&2), "{worker_ids:?}");
+        assert_eq!(inner.queue_depth.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn prune_removes_only_the_named_entry() {
+        let inner = inner_with_threshold(0.9);
+        {
+            let mut queue = inner.queue.lock().await;
+            queue.push_back(queued_with_id(1, 10));
+            queue.push_back(queued_with_id(2, 20));
+            queue.push_back(queued_with_id(3, 30));
+        }
+        inner.queue_depth.store(3, Ordering::Relaxed);
+
+        inner.prune(2).await;
+
+        let remaining_ids: Vec<u64> = inner
+            .queue
+            .lock()
+            .await
+            .iter()
+            .map(|queued| queued.id)
+            .collect();
+        assert_eq!(remaining_ids, vec![1, 3]);
+        assert_eq!(inner.queue_depth.load(Ordering::Relaxed), 2);
+    }
+
+    fn queued_with_id(id: u64, isl_tokens: usize) -> QueuedRequest {
+        let (responder, _rx) = oneshot::channel();
+        QueuedRequest {
+            id,
+            request: SchedulingRequest {
+                overlap_scores: OverlapScores::default(),
+                isl_tokens,
+            },
+            responder,
+        }
+    }
+}
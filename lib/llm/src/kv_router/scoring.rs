@@ -0,0 +1,20 @@
+// SPDX-FileCopyrightText: Copyright (c) 2024-2025 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+/// Load snapshot for a single worker endpoint, as last reported by the
+/// metrics aggregator.
+#[derive(Debug, Clone, Default)]
+pub struct Endpoint {
+    pub worker_id: i64,
+    pub gpu_cache_usage: f64,
+    pub waiting_requests: u64,
+}
+
+/// The latest known load of every worker endpoint in the namespace, keyed by
+/// worker id.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessedEndpoints {
+    pub endpoints: HashMap<i64, Endpoint>,
+}
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: Copyright (c) 2024-2025 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::sync::{mpsc, watch, Mutex};
+
+use crate::kv_router::{
+    indexer::KvIndexer, scoring::ProcessedEndpoints, worker_manager::WorkerManager,
+};
+
+/// Name the scrubber loop is registered under with the [`WorkerManager`],
+/// surfaced through `KvRouter::worker_status`.
+pub const SCRUBBER_TASK: &str = "kv-indexer-scrubber";
+
+/// Tunables for the background scrubber.
+#[derive(Debug, Clone)]
+pub struct ScrubberConfig {
+    /// How often the scrubber wakes up on its own, absent an explicit
+    /// `scrub_now()` trigger. Default: 30s
+    pub interval: Duration,
+    /// A worker's blocks are aged out once they haven't been refreshed by an
+    /// event within this long, even if the worker is still reported live.
+    /// Default: 5 minutes
+    pub ttl: Duration,
+    /// Maximum number of stale-looking workers inspected (evicted or, after
+    /// the re-check, spared) before the scrubber sleeps for `batch_sleep`, so a large cleanup doesn't monopolize CPU in one
+    /// tick. Default: 64
+    pub tranquility: usize,
+    /// How long to sleep between batches within a single tick. Default: 10ms
+    pub batch_sleep: Duration,
+}
+
+impl Default for ScrubberConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            ttl: Duration::from_secs(300),
+            tranquility: 64,
+            batch_sleep: Duration::from_millis(10),
+        }
+    }
+}
+
+/// Periodically cross-references the indexer's known worker ids against the
+/// live set reported by the metrics aggregator and evicts anything owned
+/// only by a departed or stale worker, so `find_best_match` can't keep
+/// scoring overlap against an endpoint that's gone.
+pub struct Scrubber {
+    trigger_tx: mpsc::Sender<()>,
+    evicted_total: Arc<AtomicU64>,
+}
+
+impl Scrubber {
+    pub async fn start(
+        indexer: Arc<KvIndexer>,
+        endpoints: watch::Receiver<ProcessedEndpoints>,
+        config: ScrubberConfig,
+        worker_manager: Arc<WorkerManager>,
+    ) -> Self {
+        let (trigger_tx, trigger_rx) = mpsc::channel(1);
+        let trigger_rx = Arc::new(Mutex::new(trigger_rx));
+        let evicted_total = Arc::new(AtomicU64::new(0));
+
+        worker_manager
+            .spawn(SCRUBBER_TASK, {
+                let indexer = indexer.clone();
+                let endpoints = endpoints.clone();
+                let config = config.clone();
+                let evicted_total = evicted_total.clone();
+                let trigger_rx = trigger_rx.clone();
+                move || {
+                    let indexer = indexer.clone();
+                    let endpoints = endpoints.clone();
+                    let config = config.clone();
+                    let evicted_total = evicted_total.clone();
+                    let trigger_rx = trigger_rx.clone();
+                    async move {
+                        let mut trigger_rx = trigger_rx.lock().await;
+                        loop {
+                            tokio::select! {
+                                _ = tokio::time::sleep(config.interval) => {}
+                                _ = trigger_rx.recv() => {}
+                            }
+
+                            let live_workers: HashSet<i64> =
+                                endpoints.borrow().endpoints.keys().copied().collect();
+                            let evicted = indexer
+                                .scrub(
+                                    &live_workers,
+                                    config.ttl,
+                                    config.tranquility,
+                                    config.batch_sleep,
+                                )
+                                .await;
+                            if evicted > 0 {
+                                evicted_total.fetch_add(evicted as u64, Ordering::Relaxed);
+                                tracing::info!(
+                                    evicted,
+                                    total = evicted_total.load(Ordering::Relaxed),
+                                    "scrubbed stale block entries from KV indexer"
+                                );
+                            }
+                        }
+                    }
+                }
+            })
+            .await;
+
+        Self {
+            trigger_tx,
+            evicted_total,
+        }
+    }
+
+    /// Trigger a scrub immediately instead of waiting for the next tick.
+    pub async fn scrub_now(&self) {
+        let _ = self.trigger_tx.send(()).await;
+    }
+
+    /// Total number of block hashes evicted since startup.
+    pub fn evicted_total(&self) -> u64 {
+        self.evicted_total.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,216 @@
+// SPDX-FileCopyrightText: Copyright (c) 2024-2025 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+/// Minimum backoff applied after a supervised task fails.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// Backoff is doubled on every consecutive failure, up to this ceiling.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Reported state of a task registered with a [`WorkerManager`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskState {
+    /// An attempt is running, including while it waits on a channel or
+    /// stream.
+    Busy,
+    /// Registered, but no attempt has started yet. A running attempt is
+    /// reported as `Busy`, not `Idle`, even while it is blocked on a recv.
+    Idle,
+    /// Exited cleanly and will not be restarted.
+    Dead,
+    /// The last attempt failed with this error; a retry is scheduled.
+    Errored(String),
+}
+
+struct Task {
+    state: Arc<RwLock<TaskState>>,
+}
+
+/// Supervises a set of long-lived background tasks (the KV event-ingest
+/// loop, the metrics aggregator, the scheduler's queue watcher) so that a
+/// dropped subscription or a panic doesn't silently leave routing running on
+/// a stale index. Each task is re-invoked from scratch on failure, so it can
+/// re-establish any connection it needs, with exponential backoff between
+/// attempts.
+pub struct WorkerManager {
+    cancellation_token: CancellationToken,
+    tasks: RwLock<HashMap<String, Task>>,
+}
+
+impl WorkerManager {
+    pub fn new(cancellation_token: CancellationToken) -> Self {
+        Self {
+            cancellation_token,
+            tasks: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Register and start a supervised task under `name`. `make_task` is
+    /// invoked once per attempt (including the first) so a restart can
+    /// re-subscribe rather than resume a dead stream. The task runs until it
+    /// returns `Ok(())` (treated as a clean, final exit) or the manager's
+    /// cancellation token fires.
+    pub async fn spawn<F, Fut>(&self, name: impl Into<String>, make_task: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let state = Arc::new(RwLock::new(TaskState::Idle));
+        self.tasks.write().await.insert(
+            name.clone(),
+            Task {
+                state: state.clone(),
+            },
+        );
+
+        let cancellation_token = self.cancellation_token.clone();
+        tokio::spawn(async move {
+            let mut backoff = INITIAL_BACKOFF;
+            loop {
+                if cancellation_token.is_cancelled() {
+                    *state.write().await = TaskState::Dead;
+                    return;
+                }
+
+                *state.write().await = TaskState::Busy;
+                // Run this attempt in its own task so a panic inside
+                // `make_task()` unwinds only that task, not the supervisor
+                // loop: without this, a panicking attempt would kill the
+                // whole restart loop and freeze `state` at its last value.
+                let attempt = tokio::spawn(make_task());
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => {
+                        *state.write().await = TaskState::Dead;
+                        return;
+                    }
+                    joined = attempt => {
+                        let error = match joined {
+                            Ok(Ok(())) => {
+                                *state.write().await = TaskState::Dead;
+                                return;
+                            }
+                            Ok(Err(e)) => e.to_string(),
+                            Err(join_error) if join_error.is_panic() => {
+                                format!("task panicked: {join_error}")
+                            }
+                            Err(join_error) => join_error.to_string(),
+                        };
+                        tracing::warn!(
+                            task = %name,
+                            error = %error,
+                            backoff_ms = backoff.as_millis(),
+                            "supervised task failed; restarting after backoff"
+                        );
+                        *state.write().await = TaskState::Errored(error);
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_BACKOFF);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Live state of every registered task, keyed by the name it was
+    /// registered under.
+    pub async fn status(&self) -> HashMap<String, TaskState> {
+        let tasks = self.tasks.read().await;
+        let mut out = HashMap::with_capacity(tasks.len());
+        for (name, task) in tasks.iter() {
+            out.insert(name.clone(), task.state.read().await.clone());
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    async fn wait_for_state(
+        manager: &WorkerManager,
+        name: &str,
+        predicate: impl Fn(&TaskState) -> bool,
+    ) -> TaskState {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(state) = manager.status().await.get(name) {
+                if predicate(state) {
+                    return state.clone();
+                }
+            }
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "timed out waiting for task {name} state"
+            );
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn restarts_after_error_and_eventually_finishes() {
+        let manager = WorkerManager::new(CancellationToken::new());
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        manager
+            .spawn("flaky", {
+                let attempts = attempts.clone();
+                move || {
+                    let attempts = attempts.clone();
+                    async move {
+                        if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                            anyhow::bail!("transient failure");
+                        }
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+
+        let state = wait_for_state(&manager, "flaky", |s| matches!(s, TaskState::Dead)).await;
+        assert_eq!(state, TaskState::Dead);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn panic_is_isolated_and_task_is_restarted() {
+        let manager = WorkerManager::new(CancellationToken::new());
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        manager
+            .spawn("panics-once", {
+                let attempts = attempts.clone();
+                move || {
+                    let attempts = attempts.clone();
+                    async move {
+                        if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                            panic!("boom");
+                        }
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+
+        // The panicking attempt must surface as Errored (and the restart
+        // loop must keep running), not silently freeze the supervisor task.
+        let errored = wait_for_state(&manager, "panics-once", |s| {
+            matches!(s, TaskState::Errored(_))
+        })
+        .await;
+        match errored {
+            TaskState::Errored(message) => assert!(message.contains("panic")),
+            other => panic!("expected Errored, got {other:?}"),
+        }
+
+        let state = wait_for_state(&manager, "panics-once", |s| matches!(s, TaskState::Dead)).await;
+        assert_eq!(state, TaskState::Dead);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}